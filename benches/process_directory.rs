@@ -1,24 +1,18 @@
-use criterion::{Criterion, criterion_group, criterion_main};
+use criterion::{criterion_group, criterion_main, Criterion};
 
-use log::warn;
-use rori::process_directory;
+use rori::{process_directory, ProcessOptions};
 
 fn bench_dry_process_directory(c: &mut Criterion) {
     let path = std::path::Path::new("benches/test_data/region_small");
-    let dry_run = true;
-    let inhabited_time = 100;
-
-    // Benchmarks are subjective to the current system and its capabilities.
-    rayon::ThreadPoolBuilder::new()
-        .num_threads(num_cpus::get())
-        .build_global()
-        .unwrap_or_else(|e| {
-            warn!("Failed to set thread pool size: {}, using default", e);
-        });
+    let options = ProcessOptions::builder()
+        .threads(num_cpus::get())
+        .dry_run(true)
+        .inhabited_time(100)
+        .build();
 
     c.bench_function("process_directory", |b| {
         b.iter(|| {
-            process_directory(path, dry_run, inhabited_time, false).unwrap();
+            process_directory(path, &options).unwrap();
         });
     });
 }
@@ -0,0 +1,23 @@
+use crate::parser;
+use crate::predicate::TagValues;
+
+/// Fields a chunk needs for [`is_complete`], queried alongside whatever the active
+/// `RetentionPredicate` asks for so completeness is checked in the same NBT scan.
+pub const STRUCTURAL_TAGS: [&str; 5] = ["xPos", "zPos", "sections", "Sections", "Status"];
+
+/// A chunk is structurally complete when it has well-typed `xPos`/`zPos` (TAG_INT), a sections
+/// list under either its modern (`sections`) or pre-1.18 (`Sections`) name (TAG_LIST), and a
+/// `Status` string (TAG_STRING). Anything else usually means a half-generated or truncated chunk.
+pub fn is_complete(values: &TagValues) -> bool {
+    let has_int =
+        |name: &str| matches!(values.get(name), Some(v) if v.tag_type() == parser::TAG_INT);
+    let has_list =
+        |name: &str| matches!(values.get(name), Some(v) if v.tag_type() == parser::TAG_LIST);
+    let has_string =
+        |name: &str| matches!(values.get(name), Some(v) if v.tag_type() == parser::TAG_STRING);
+
+    has_int("xPos")
+        && has_int("zPos")
+        && (has_list("sections") || has_list("Sections"))
+        && has_string("Status")
+}
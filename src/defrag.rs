@@ -0,0 +1,222 @@
+use std::collections::HashSet;
+use std::io::{Seek, SeekFrom, Write};
+use std::path::Path;
+
+const HEADER_SIZE: usize = 8192;
+const SECTOR_SIZE: usize = 4096;
+const LOCATION_TABLE_ENTRIES: usize = 1024;
+const FIRST_DATA_SECTOR: u32 = 2;
+
+#[derive(Debug)]
+pub enum DefragError {
+    IoError(std::io::Error),
+    InvalidEntry(String),
+}
+
+impl From<std::io::Error> for DefragError {
+    fn from(error: std::io::Error) -> Self {
+        DefragError::IoError(error)
+    }
+}
+
+impl std::fmt::Display for DefragError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DefragError::IoError(e) => write!(f, "IO error: {}", e),
+            DefragError::InvalidEntry(msg) => write!(f, "Invalid location table entry: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for DefragError {}
+
+#[derive(Debug, Default)]
+pub struct DefragReport {
+    pub chunks_moved: u32,
+    pub bytes_reclaimed: u64,
+}
+
+struct Entry {
+    index: usize,
+    old_offset: u32,
+    sector_count: u8,
+}
+
+/// Compact a region in place: chunks in `drop` are removed and every surviving chunk's payload
+/// is shifted toward the front of the file to reclaim the sectors they freed, writing only the
+/// sectors that actually moved. Repeated invocations converge, since a chunk already at its
+/// target offset is left untouched.
+pub fn defragment(
+    path: &Path,
+    drop: &HashSet<(usize, usize)>,
+) -> Result<DefragReport, DefragError> {
+    let mut data = std::fs::read(path)?;
+    let mut report = DefragReport::default();
+
+    if data.len() < HEADER_SIZE {
+        return Ok(report);
+    }
+
+    let mut survivors = Vec::new();
+    for index in 0..LOCATION_TABLE_ENTRIES {
+        let entry = &data[index * 4..index * 4 + 4];
+        let old_offset = u32::from_be_bytes([0, entry[0], entry[1], entry[2]]);
+        let sector_count = entry[3];
+
+        if old_offset == 0 && sector_count == 0 {
+            continue;
+        }
+
+        let x = index % 32;
+        let z = index / 32;
+        if drop.contains(&(x, z)) {
+            continue;
+        }
+
+        let old_start = old_offset as usize * SECTOR_SIZE;
+        let span = sector_count as usize * SECTOR_SIZE;
+        if sector_count == 0 || old_start + span > data.len() {
+            return Err(DefragError::InvalidEntry(format!(
+                "chunk ({}, {}) declares an out-of-bounds sector range",
+                x, z
+            )));
+        }
+
+        survivors.push(Entry {
+            index,
+            old_offset,
+            sector_count,
+        });
+    }
+
+    // Chunks must be walked in their current on-disk order so a chunk is never shifted past one
+    // that hasn't been relocated yet, which would otherwise overwrite unread data.
+    survivors.sort_by_key(|entry| entry.old_offset);
+
+    let mut next_sector = FIRST_DATA_SECTOR;
+    let mut last_used_sector = FIRST_DATA_SECTOR;
+    let mut moved_spans = Vec::new();
+
+    for entry in &survivors {
+        let new_offset = next_sector;
+        next_sector += entry.sector_count as u32;
+        last_used_sector = next_sector;
+
+        if new_offset != entry.old_offset {
+            let old_start = entry.old_offset as usize * SECTOR_SIZE;
+            let new_start = new_offset as usize * SECTOR_SIZE;
+            let span = entry.sector_count as usize * SECTOR_SIZE;
+
+            data.copy_within(old_start..old_start + span, new_start);
+            moved_spans.push(new_start..new_start + span);
+            report.chunks_moved += 1;
+        }
+
+        let location = &mut data[entry.index * 4..entry.index * 4 + 4];
+        let offset_bytes = new_offset.to_be_bytes();
+        location[0] = offset_bytes[1];
+        location[1] = offset_bytes[2];
+        location[2] = offset_bytes[3];
+        location[3] = entry.sector_count;
+    }
+
+    for &(x, z) in drop {
+        let index = z * 32 + x;
+        data[index * 4..index * 4 + 4].fill(0);
+    }
+
+    let new_len = last_used_sector as usize * SECTOR_SIZE;
+    report.bytes_reclaimed = data.len().saturating_sub(new_len) as u64;
+    data.truncate(new_len);
+
+    // Only the location table and the sectors of chunks that moved differ from the file on disk;
+    // the timestamp table and chunks left in place are not rewritten.
+    let mut file = std::fs::OpenOptions::new().write(true).open(path)?;
+    file.write_all(&data[..LOCATION_TABLE_ENTRIES * 4])?;
+    for span in moved_spans {
+        file.seek(SeekFrom::Start(span.start as u64))?;
+        file.write_all(&data[span])?;
+    }
+    file.set_len(new_len as u64)?;
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Build a minimal region buffer with one chunk per `(index, sector_offset, sector_count)`
+    /// triple in `chunks`, each chunk's payload sectors filled with its index as a marker byte so
+    /// tests can tell which chunk ended up where after compaction.
+    fn region_with_chunks(chunks: &[(usize, u32, u8)], total_sectors: u32) -> Vec<u8> {
+        let mut data = vec![0u8; total_sectors as usize * SECTOR_SIZE];
+        for &(index, sector_offset, sector_count) in chunks {
+            let offset_bytes = sector_offset.to_be_bytes();
+            let entry = &mut data[index * 4..index * 4 + 4];
+            entry[0] = offset_bytes[1];
+            entry[1] = offset_bytes[2];
+            entry[2] = offset_bytes[3];
+            entry[3] = sector_count;
+
+            let start = sector_offset as usize * SECTOR_SIZE;
+            let span = sector_count as usize * SECTOR_SIZE;
+            data[start..start + span].fill(index as u8);
+        }
+        data
+    }
+
+    #[test]
+    fn compacts_surviving_chunks_forward() {
+        let tmp = std::env::temp_dir().join(format!("defrag_test_{}.mca", std::process::id()));
+        // Chunk 0 at sector 2, a gap (as if chunk 1 was already removed), chunk 2 at sector 5.
+        let data = region_with_chunks(&[(0, FIRST_DATA_SECTOR, 1), (2, 5, 1)], 6);
+        std::fs::write(&tmp, &data).unwrap();
+
+        let report = defragment(&tmp, &HashSet::new()).unwrap();
+        assert_eq!(report.chunks_moved, 1);
+
+        let result = std::fs::read(&tmp).unwrap();
+        assert_eq!(result.len(), (FIRST_DATA_SECTOR as usize + 2) * SECTOR_SIZE);
+
+        let entry0 = &result[0..4];
+        assert_eq!(entry0[2], FIRST_DATA_SECTOR as u8);
+        let entry2 = &result[2 * 4..2 * 4 + 4];
+        assert_eq!(entry2[2], FIRST_DATA_SECTOR as u8 + 1);
+
+        std::fs::remove_file(&tmp).unwrap();
+    }
+
+    #[test]
+    fn dropped_chunks_are_excluded_and_cleared() {
+        let tmp = std::env::temp_dir().join(format!("defrag_test_drop_{}.mca", std::process::id()));
+        let data = region_with_chunks(&[(0, FIRST_DATA_SECTOR, 1), (1, 3, 1)], 4);
+        std::fs::write(&tmp, &data).unwrap();
+
+        let mut drop = HashSet::new();
+        drop.insert((1, 0));
+        let report = defragment(&tmp, &drop).unwrap();
+        assert_eq!(report.chunks_moved, 0);
+
+        let result = std::fs::read(&tmp).unwrap();
+        assert_eq!(result.len(), (FIRST_DATA_SECTOR as usize + 1) * SECTOR_SIZE);
+        assert_eq!(&result[4..8], &[0, 0, 0, 0]);
+
+        std::fs::remove_file(&tmp).unwrap();
+    }
+
+    #[test]
+    fn out_of_bounds_entry_is_rejected_not_panicked() {
+        let tmp = std::env::temp_dir().join(format!("defrag_test_oob_{}.mca", std::process::id()));
+        let mut data = vec![0u8; HEADER_SIZE + SECTOR_SIZE];
+        // Chunk (0, 0) claims 10 sectors from sector 2, running past the end of this tiny file.
+        data[2] = 2;
+        data[3] = 10;
+        std::fs::write(&tmp, &data).unwrap();
+
+        let result = defragment(&tmp, &HashSet::new());
+        assert!(matches!(result, Err(DefragError::InvalidEntry(_))));
+
+        std::fs::remove_file(&tmp).unwrap();
+    }
+}
@@ -1,15 +1,24 @@
+mod completeness;
+mod defrag;
 mod parser;
+mod predicate;
+mod scan;
 
 use indicatif::{ProgressBar, ProgressStyle};
 use log::{debug, error, info, trace, warn};
 use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
 use std::{
+    collections::HashSet,
     fs,
     io::BufReader,
     path::{Path, PathBuf},
     time::Instant,
 };
 
+pub use parser::TagValue;
+pub use predicate::{All, Any, InhabitedTimePredicate, RetentionPredicate, TagValues};
+pub use scan::CorruptionStats;
+
 #[derive(Debug)]
 pub enum ProcessError {
     IoError(std::io::Error),
@@ -35,24 +44,30 @@ impl From<std::io::Error> for ProcessError {
     }
 }
 
-#[derive(Debug, Default)]
-struct ChunkStats {
-    total_chunks: u32,
-    inhabited_chunks: u32,
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ChunkStats {
+    pub total_chunks: u32,
+    pub kept_chunks: u32,
+    pub incomplete_chunks: u32,
 }
 
 impl ChunkStats {
     fn merge(&mut self, other: ChunkStats) {
         self.total_chunks += other.total_chunks;
-        self.inhabited_chunks += other.inhabited_chunks;
+        self.kept_chunks += other.kept_chunks;
+        self.incomplete_chunks += other.incomplete_chunks;
     }
 }
 
+/// Aggregated outcome of a [`process_directory`] run, returned so callers embedding `rori` can
+/// inspect or report on it instead of only seeing the `log` output.
 #[derive(Debug, Default)]
-struct RegionStats {
-    total_regions: u32,
-    deleted_regions: u32,
-    chunk_stats: ChunkStats,
+pub struct RegionStats {
+    pub total_regions: u32,
+    pub deleted_regions: u32,
+    pub chunk_stats: ChunkStats,
+    pub corruption_stats: CorruptionStats,
+    pub bytes_reclaimed: u64,
 }
 
 impl RegionStats {
@@ -60,15 +75,147 @@ impl RegionStats {
         self.total_regions += other.total_regions;
         self.deleted_regions += other.deleted_regions;
         self.chunk_stats.merge(other.chunk_stats);
+        self.corruption_stats.merge(other.corruption_stats);
+        self.bytes_reclaimed += other.bytes_reclaimed;
+    }
+
+    /// Render these stats as a single-line JSON object, for the `--report` flag and other
+    /// scripted consumers. Hand-rolled rather than pulled in via a dependency, since every field
+    /// here is a plain integer. `elapsed` is the wall-clock time the run took, as measured by the
+    /// caller.
+    pub fn to_json(&self, elapsed: std::time::Duration) -> String {
+        format!(
+            "{{\"total_regions\":{},\"deleted_regions\":{},\"total_chunks\":{},\"kept_chunks\":{},\"incomplete_chunks\":{},\"corrupt_chunks\":{},\"bad_offset\":{},\"oversized\":{},\"bad_compression\":{},\"inflate_failed\":{},\"overlapping\":{},\"bytes_reclaimed\":{},\"elapsed_secs\":{:.3}}}",
+            self.total_regions,
+            self.deleted_regions,
+            self.chunk_stats.total_chunks,
+            self.chunk_stats.kept_chunks,
+            self.chunk_stats.incomplete_chunks,
+            self.corruption_stats.total(),
+            self.corruption_stats.bad_offset,
+            self.corruption_stats.oversized,
+            self.corruption_stats.bad_compression,
+            self.corruption_stats.inflate_failed,
+            self.corruption_stats.overlapping,
+            self.bytes_reclaimed,
+            elapsed.as_secs_f64(),
+        )
     }
 }
 
-pub fn process_directory(
-    path: &Path,
+/// Configures a [`process_directory`] run. Build one with [`ProcessOptions::builder`]; unset
+/// fields default to the same behavior as the CLI's own defaults (keep chunks with an
+/// `InhabitedTime` over 100, one thread per core, no destructive flags).
+pub struct ProcessOptions {
+    threads: usize,
     dry_run: bool,
-    inhabited_time: u32,
     delete_regions: bool,
-) -> Result<(), ProcessError> {
+    delete_corrupted: bool,
+    defragment: bool,
+    delete_incomplete: bool,
+    predicate: Box<dyn RetentionPredicate>,
+}
+
+impl ProcessOptions {
+    pub fn builder() -> ProcessOptionsBuilder {
+        ProcessOptionsBuilder::default()
+    }
+}
+
+pub struct ProcessOptionsBuilder {
+    threads: usize,
+    dry_run: bool,
+    delete_regions: bool,
+    delete_corrupted: bool,
+    defragment: bool,
+    delete_incomplete: bool,
+    predicate: Box<dyn RetentionPredicate>,
+}
+
+impl Default for ProcessOptionsBuilder {
+    fn default() -> Self {
+        Self {
+            threads: num_cpus::get(),
+            dry_run: false,
+            delete_regions: false,
+            delete_corrupted: false,
+            defragment: false,
+            delete_incomplete: false,
+            predicate: Box::new(InhabitedTimePredicate::new(100)),
+        }
+    }
+}
+
+impl ProcessOptionsBuilder {
+    /// Size of the rayon thread pool used to process regions in parallel.
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = threads;
+        self
+    }
+
+    /// Simulate processing without writing anything to disk.
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Shorthand for `predicate(Box::new(InhabitedTimePredicate::new(threshold)))`.
+    pub fn inhabited_time(mut self, threshold: i64) -> Self {
+        self.predicate = Box::new(InhabitedTimePredicate::new(threshold));
+        self
+    }
+
+    /// Replace the default retention predicate entirely, e.g. with an [`Any`] or [`All`]
+    /// combinator over several predicates.
+    pub fn predicate(mut self, predicate: Box<dyn RetentionPredicate>) -> Self {
+        self.predicate = predicate;
+        self
+    }
+
+    pub fn delete_regions(mut self, delete_regions: bool) -> Self {
+        self.delete_regions = delete_regions;
+        self
+    }
+
+    pub fn delete_corrupted(mut self, delete_corrupted: bool) -> Self {
+        self.delete_corrupted = delete_corrupted;
+        self
+    }
+
+    pub fn defragment(mut self, defragment: bool) -> Self {
+        self.defragment = defragment;
+        self
+    }
+
+    pub fn delete_incomplete(mut self, delete_incomplete: bool) -> Self {
+        self.delete_incomplete = delete_incomplete;
+        self
+    }
+
+    pub fn build(self) -> ProcessOptions {
+        ProcessOptions {
+            threads: self.threads,
+            dry_run: self.dry_run,
+            delete_regions: self.delete_regions,
+            delete_corrupted: self.delete_corrupted,
+            defragment: self.defragment,
+            delete_incomplete: self.delete_incomplete,
+            predicate: self.predicate,
+        }
+    }
+}
+
+pub fn process_directory(
+    path: &Path,
+    options: &ProcessOptions,
+) -> Result<RegionStats, ProcessError> {
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(options.threads)
+        .build_global()
+        .unwrap_or_else(|e| {
+            warn!("Failed to set thread pool size: {}, using default", e);
+        });
+
     let start = Instant::now();
     let regions = find_region_files(path)?;
     debug!(
@@ -80,7 +227,7 @@ pub fn process_directory(
 
     if regions.is_empty() {
         warn!("No .mca files found in directory: {}", path.display());
-        return Ok(());
+        return Ok(RegionStats::default());
     }
 
     let pb = ProgressBar::new(regions.len() as u64);
@@ -92,7 +239,15 @@ pub fn process_directory(
     let results: Vec<Result<RegionStats, ProcessError>> = regions
         .par_iter()
         .map(|region_path| {
-            let res = process_region(region_path, dry_run, inhabited_time, delete_regions);
+            let res = process_region(
+                region_path,
+                options.dry_run,
+                options.predicate.as_ref(),
+                options.delete_regions,
+                options.delete_corrupted,
+                options.defragment,
+                options.delete_incomplete,
+            );
             pb.inc(1);
             res
         })
@@ -111,9 +266,8 @@ pub fn process_directory(
         }
     }
 
-    let inhabited_percentage = if total_stats.chunk_stats.total_chunks > 0 {
-        (total_stats.chunk_stats.inhabited_chunks as f64)
-            / total_stats.chunk_stats.total_chunks as f64
+    let kept_percentage = if total_stats.chunk_stats.total_chunks > 0 {
+        (total_stats.chunk_stats.kept_chunks as f64) / total_stats.chunk_stats.total_chunks as f64
             * 100.0
     } else {
         0.0
@@ -124,10 +278,30 @@ pub fn process_directory(
         total_stats.total_regions, total_stats.chunk_stats.total_chunks
     );
     info!(
-        "Inhabited chunks: {} ({}%)",
-        total_stats.chunk_stats.inhabited_chunks, inhabited_percentage
+        "Kept chunks: {} ({}%)",
+        total_stats.chunk_stats.kept_chunks, kept_percentage
     );
-    Ok(())
+    if total_stats.chunk_stats.incomplete_chunks > 0 {
+        info!(
+            "Incomplete chunks: {}",
+            total_stats.chunk_stats.incomplete_chunks
+        );
+    }
+    if total_stats.corruption_stats.total() > 0 {
+        info!(
+            "Corrupt chunks: {} (bad offset: {}, oversized: {}, bad compression: {}, inflate failed: {}, overlapping: {})",
+            total_stats.corruption_stats.total(),
+            total_stats.corruption_stats.bad_offset,
+            total_stats.corruption_stats.oversized,
+            total_stats.corruption_stats.bad_compression,
+            total_stats.corruption_stats.inflate_failed,
+            total_stats.corruption_stats.overlapping,
+        );
+    }
+    if total_stats.bytes_reclaimed > 0 {
+        info!("Bytes reclaimed: {}", total_stats.bytes_reclaimed);
+    }
+    Ok(total_stats)
 }
 
 fn find_region_files(path: &Path) -> Result<Vec<PathBuf>, ProcessError> {
@@ -149,11 +323,31 @@ fn find_region_files(path: &Path) -> Result<Vec<PathBuf>, ProcessError> {
 fn process_region(
     region_path: &Path,
     dry_run: bool,
-    threshold: u32,
+    predicate: &dyn RetentionPredicate,
     delete_regions: bool,
+    delete_corrupted: bool,
+    defragment: bool,
+    delete_incomplete: bool,
 ) -> Result<RegionStats, ProcessError> {
     trace!("Processing region: {}", region_path.display());
 
+    let raw = fs::read(region_path)?;
+    let scan_report = scan::scan_region(&raw).map_err(|e| {
+        ProcessError::RegionError(format!(
+            "Failed to scan header of {}: {}",
+            region_path.display(),
+            e
+        ))
+    })?;
+    let corrupt: HashSet<(usize, usize)> = scan_report.corrupt_chunks.iter().copied().collect();
+    if scan_report.stats.total() > 0 {
+        warn!(
+            "Found {} corrupt chunk(s) in {}",
+            scan_report.stats.total(),
+            region_path.display()
+        );
+    }
+
     let file = fs::File::open(region_path)?;
     let reader = BufReader::new(file);
 
@@ -166,24 +360,42 @@ fn process_region(
         ))
     })?;
 
+    let mut targets = predicate.required_tags();
+    for tag in completeness::STRUCTURAL_TAGS {
+        if !targets.contains(&tag) {
+            targets.push(tag);
+        }
+    }
     let mut chunk_stats = ChunkStats::default();
     let mut deleted_count = 0;
+    let mut dropped: HashSet<(usize, usize)> = HashSet::new();
 
     // First pass: determine which chunks to keep
     for x in 0..32 {
         for z in 0..32 {
+            if corrupt.contains(&(x, z)) {
+                continue;
+            }
+
             if let Ok(Some(chunk_data)) = mca.read_chunk(x, z) {
                 chunk_stats.total_chunks += 1;
 
-                let inhabited_time = parser::process_chunk(&chunk_data).map_err(|e| {
+                let values = parser::process_chunk(&chunk_data, &targets).map_err(|e| {
                     ProcessError::ChunkError(format!("Failed to process chunk: {}", e))
                 })?;
+                let lookup = TagValues::new(&targets, &values);
 
-                if inhabited_time.is_some() && inhabited_time.unwrap() > threshold as i64 {
-                    chunk_stats.inhabited_chunks += 1;
+                let complete = completeness::is_complete(&lookup);
+                if !complete {
+                    chunk_stats.incomplete_chunks += 1;
+                }
+
+                if predicate.should_keep(&lookup) && (complete || !delete_incomplete) {
+                    chunk_stats.kept_chunks += 1;
                     chunks[x][z] = Some(chunk_data.clone());
                 } else {
                     deleted_count += 1;
+                    dropped.insert((x, z));
                 }
             }
         }
@@ -193,14 +405,16 @@ fn process_region(
         total_regions: 1,
         deleted_regions: 0,
         chunk_stats: ChunkStats::default(),
+        corruption_stats: scan_report.stats,
+        bytes_reclaimed: 0,
     };
     region_stats.chunk_stats.merge(chunk_stats);
 
+    let mut rebuilt = false;
+
     if delete_regions {
-        // In region deletion mode, delete the entire region if no inhabited chunks
-        if region_stats.chunk_stats.inhabited_chunks == 0
-            && region_stats.chunk_stats.total_chunks > 0
-        {
+        // In region deletion mode, delete the entire region if no chunks survived the predicate
+        if region_stats.chunk_stats.kept_chunks == 0 && region_stats.chunk_stats.total_chunks > 0 {
             if !dry_run {
                 fs::remove_file(region_path)?;
                 debug!("Deleted region file: {}", region_path.display());
@@ -208,9 +422,50 @@ fn process_region(
                 debug!("Would delete region file: {}", region_path.display());
             }
             region_stats.deleted_regions = 1;
+            region_stats.bytes_reclaimed = raw.len() as u64;
+        }
+    } else if defragment {
+        // Defragment mode: shift surviving payloads forward in place instead of rewriting the
+        // whole file, reclaiming the sectors freed by pruned chunks.
+        let mut to_drop = dropped;
+        if delete_corrupted {
+            to_drop.extend(scan_report.corrupt_chunks.iter().copied());
+        } else {
+            // Only force-drop a corrupt chunk whose location-table entry is itself out of
+            // bounds (the `bad_offset` case): that offset/sector-count can't be trusted enough
+            // to feed into the byte-shifting code below. A chunk corrupt for any other reason
+            // (overlapping claim, bad compression byte, failed inflate) still has a
+            // structurally valid sector range, so it's left out of `to_drop` and moved forward
+            // like any other survivor instead of being discarded - mirroring how the
+            // non-defragment rebuild path preserves these via `scan::preserve_corrupted`.
+            to_drop.extend(
+                scan_report
+                    .corrupt_chunks
+                    .iter()
+                    .copied()
+                    .filter(|&(x, z)| !scan::location_in_bounds(&raw, x, z)),
+            );
+        }
+
+        if !dry_run && !to_drop.is_empty() {
+            let report = defrag::defragment(region_path, &to_drop).map_err(|e| {
+                ProcessError::RegionError(format!(
+                    "Failed to defragment {}: {}",
+                    region_path.display(),
+                    e
+                ))
+            })?;
+            rebuilt = true;
+            region_stats.bytes_reclaimed = report.bytes_reclaimed;
+            debug!(
+                "Defragmented {}: moved {} chunk(s), reclaimed {} bytes",
+                region_path.display(),
+                report.chunks_moved,
+                report.bytes_reclaimed
+            );
         }
     } else {
-        // In chunk deletion mode, rebuild the region with only inhabited chunks
+        // In chunk deletion mode, rebuild the region with only the chunks we kept
         if !dry_run && deleted_count > 0 {
             let temp_path = format!("{}-temp.mca", region_path.display());
             let temp_file = std::fs::OpenOptions::new()
@@ -235,8 +490,29 @@ fn process_region(
                 }
             }
 
+            drop(new_region);
+
             // Replace original file with the compacted version
             fs::rename(&temp_path, region_path)?;
+            rebuilt = true;
+
+            // The rebuild above only wrote the chunks the predicate kept, so any corrupt chunk
+            // was dropped along with it - carry its raw bytes over unless --delete-corrupted was
+            // explicitly asked to remove it too.
+            if !delete_corrupted && !scan_report.corrupt_chunks.is_empty() {
+                scan::preserve_corrupted(region_path, &raw, &scan_report.corrupt_chunks).map_err(
+                    |e| {
+                        ProcessError::RegionError(format!(
+                            "Failed to preserve corrupted chunks in {}: {}",
+                            region_path.display(),
+                            e
+                        ))
+                    },
+                )?;
+            }
+
+            let new_len = fs::metadata(region_path)?.len();
+            region_stats.bytes_reclaimed = (raw.len() as u64).saturating_sub(new_len);
 
             debug!(
                 "Deleted {} chunks from {} (compacted)",
@@ -246,6 +522,100 @@ fn process_region(
         }
     }
 
+    // The rebuild/defragment paths above already drop corrupt chunks when they run; only zero
+    // their location-table entries directly when neither ran and the region file still exists.
+    if delete_corrupted
+        && !dry_run
+        && !rebuilt
+        && region_stats.deleted_regions == 0
+        && !scan_report.corrupt_chunks.is_empty()
+    {
+        scan::delete_corrupted(region_path, &scan_report.corrupt_chunks).map_err(|e| {
+            ProcessError::RegionError(format!(
+                "Failed to delete corrupted chunks in {}: {}",
+                region_path.display(),
+                e
+            ))
+        })?;
+        debug!(
+            "Deleted {} corrupted chunk(s) from {}",
+            scan_report.corrupt_chunks.len(),
+            region_path.display()
+        );
+    }
+
     trace!("Region {} stats: {:?}", region_path.display(), region_stats);
     Ok(region_stats)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECTOR_SIZE: usize = 4096;
+
+    /// A minimal NBT chunk payload: a root compound with a single `InhabitedTime` long tag.
+    fn minimal_chunk_nbt(inhabited_time: i64) -> Vec<u8> {
+        let mut data = vec![10u8]; // TAG_COMPOUND
+        data.extend_from_slice(&0u16.to_be_bytes()); // root name, empty
+
+        data.push(4); // TAG_LONG
+        let name = b"InhabitedTime";
+        data.extend_from_slice(&(name.len() as u16).to_be_bytes());
+        data.extend_from_slice(name);
+        data.extend_from_slice(&inhabited_time.to_be_bytes());
+
+        data.push(0); // TAG_END
+        data
+    }
+
+    /// Flip the compression byte of the chunk at `(x, z)` in `path` to an invalid value, leaving
+    /// its location-table entry (and therefore its sector range) untouched.
+    fn corrupt_compression_byte(path: &Path, x: usize, z: usize) {
+        let mut data = fs::read(path).unwrap();
+        let index = z * 32 + x;
+        let entry = &data[index * 4..index * 4 + 4];
+        let sector_offset = u32::from_be_bytes([0, entry[0], entry[1], entry[2]]) as usize;
+        let payload_start = sector_offset * SECTOR_SIZE;
+        data[payload_start + 4] = 0xFF;
+        fs::write(path, &data).unwrap();
+    }
+
+    #[test]
+    fn defragment_without_delete_corrupted_preserves_in_bounds_corrupt_chunk() {
+        let path = std::env::temp_dir().join(format!(
+            "rori_defrag_preserve_test_{}.mca",
+            std::process::id()
+        ));
+
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .write(true)
+            .read(true)
+            .truncate(true)
+            .open(&path)
+            .unwrap();
+        let mut region = fastanvil::Region::new(file).unwrap();
+        // Dropped by the InhabitedTime predicate below.
+        region.write_chunk(0, 0, &minimal_chunk_nbt(10)).unwrap();
+        // Would otherwise survive, but its compression byte is corrupted after writing.
+        region.write_chunk(1, 0, &minimal_chunk_nbt(1000)).unwrap();
+        drop(region);
+
+        corrupt_compression_byte(&path, 1, 0);
+
+        let predicate = InhabitedTimePredicate::new(100);
+        let result = process_region(&path, false, &predicate, false, false, true, false).unwrap();
+        assert_eq!(result.corruption_stats.bad_compression, 1);
+
+        let data = fs::read(&path).unwrap();
+        let dropped_entry = &data[0..4];
+        assert_eq!(dropped_entry, &[0, 0, 0, 0]);
+
+        let corrupt_index = 1; // chunk (1, 0)
+        let corrupt_entry = &data[corrupt_index * 4..corrupt_index * 4 + 4];
+        assert_ne!(corrupt_entry, &[0, 0, 0, 0]);
+
+        fs::remove_file(&path).unwrap();
+    }
+}
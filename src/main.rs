@@ -1,8 +1,8 @@
-use std::{path::PathBuf, process};
+use std::{fs, path::PathBuf, process};
 
 use clap::Parser;
-use log::{debug, error, info, warn};
-use rori::process_directory;
+use log::{debug, error, info};
+use rori::ProcessOptions;
 
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
@@ -29,6 +29,24 @@ struct Args {
     /// Delete entire regions instead of individual chunks when no inhabited chunks exist
     #[arg(long)]
     delete_regions: bool,
+
+    /// Validate the raw Anvil header of each region and remove chunks that fail the check
+    #[arg(long)]
+    delete_corrupted: bool,
+
+    /// Compact regions in place by shifting surviving chunks forward instead of rewriting the
+    /// whole file through a temp copy
+    #[arg(long)]
+    defragment: bool,
+
+    /// Treat chunks missing required structural tags (xPos/zPos/sections/Status) as if the
+    /// predicate rejected them
+    #[arg(long)]
+    delete_incomplete: bool,
+
+    /// Write the final run statistics as JSON to this path, for scripts and CI pipelines
+    #[arg(long)]
+    report: Option<PathBuf>,
 }
 
 fn main() {
@@ -57,14 +75,6 @@ fn main() {
         process::exit(1);
     }
 
-    // Set thread pool size
-    rayon::ThreadPoolBuilder::new()
-        .num_threads(args.threads)
-        .build_global()
-        .unwrap_or_else(|e| {
-            warn!("Failed to set thread pool size: {}, using default", e);
-        });
-
     debug!(
         "Using {} threads w/SIMD {}",
         args.threads,
@@ -74,18 +84,33 @@ fn main() {
     // Start timing
     let start = std::time::Instant::now();
 
-    if let Err(e) = process_directory(
-        &args.path,
-        args.dry_run,
-        args.inhabited_time,
-        args.delete_regions,
-    ) {
-        error!("Processing failed: {}", e);
-        process::exit(1);
-    }
+    let options = ProcessOptions::builder()
+        .threads(args.threads)
+        .dry_run(args.dry_run)
+        .inhabited_time(args.inhabited_time as i64)
+        .delete_regions(args.delete_regions)
+        .delete_corrupted(args.delete_corrupted)
+        .defragment(args.defragment)
+        .delete_incomplete(args.delete_incomplete)
+        .build();
+
+    let stats = match rori::process_directory(&args.path, &options) {
+        Ok(stats) => stats,
+        Err(e) => {
+            error!("Processing failed: {}", e);
+            process::exit(1);
+        }
+    };
 
     let duration = start.elapsed();
     info!("Processing completed in {:.2?}", duration);
+
+    if let Some(report_path) = &args.report {
+        if let Err(e) = fs::write(report_path, stats.to_json(duration)) {
+            error!("Failed to write report to {}: {}", report_path.display(), e);
+            process::exit(1);
+        }
+    }
 }
 
 fn init_logging(verbose: u8) -> Result<(), Box<dyn std::error::Error>> {
@@ -30,19 +30,71 @@ impl std::error::Error for NbtError {}
 const TAG_END: u8 = 0;
 const TAG_BYTE: u8 = 1;
 const TAG_SHORT: u8 = 2;
-const TAG_INT: u8 = 3;
+pub(crate) const TAG_INT: u8 = 3;
 const TAG_LONG: u8 = 4;
 const TAG_FLOAT: u8 = 5;
 const TAG_DOUBLE: u8 = 6;
 const TAG_BYTE_ARRAY: u8 = 7;
-const TAG_STRING: u8 = 8;
-const TAG_LIST: u8 = 9;
+pub(crate) const TAG_STRING: u8 = 8;
+pub(crate) const TAG_LIST: u8 = 9;
 const TAG_COMPOUND: u8 = 10;
 const TAG_INT_ARRAY: u8 = 11;
 const TAG_LONG_ARRAY: u8 = 12;
 
-/// This parser only searches for the specific field and skips everything else
-pub fn extract_inhabited_time(chunk_data: &[u8]) -> Result<Option<i64>, NbtError> {
+/// Longest tag name this parser will compare against a target; names beyond this are skipped
+/// unread since nothing we query for is anywhere near this long.
+const MAX_TAG_NAME_LEN: usize = 32;
+
+/// Deepest a `Level` compound will be descended into looking for nested targets. Real worlds only
+/// ever nest one `Level` deep (pre-1.18 wraps chunk fields in a single `Level` compound); a
+/// `Level` nested deeper than this limit - corrupted or adversarially crafted - is skipped rather
+/// than recursed into indefinitely, since nothing here bounds the NBT payload's structure before
+/// this point.
+const MAX_LEVEL_DEPTH: u8 = 2;
+
+/// A value read out of a chunk's NBT by [`query_tags`]. Scalar types are parsed in place; every
+/// other type (strings, lists, compounds, arrays) is left unparsed as `Other` since callers that
+/// ask for them are usually only checking presence or tag type (see [`TagValue::tag_type`]).
+#[derive(Debug, Clone, Copy)]
+pub enum TagValue {
+    Byte(i8),
+    Short(i16),
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    Other(u8),
+}
+
+impl TagValue {
+    pub fn as_i64(self) -> Option<i64> {
+        match self {
+            TagValue::Byte(v) => Some(v as i64),
+            TagValue::Short(v) => Some(v as i64),
+            TagValue::Int(v) => Some(v as i64),
+            TagValue::Long(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub fn tag_type(self) -> u8 {
+        match self {
+            TagValue::Byte(_) => TAG_BYTE,
+            TagValue::Short(_) => TAG_SHORT,
+            TagValue::Int(_) => TAG_INT,
+            TagValue::Long(_) => TAG_LONG,
+            TagValue::Float(_) => TAG_FLOAT,
+            TagValue::Double(_) => TAG_DOUBLE,
+            TagValue::Other(tag_type) => tag_type,
+        }
+    }
+}
+
+/// Scan a chunk's NBT for a fixed set of target field names in one pass, skipping every other
+/// tag without allocating. Returns one slot per entry in `targets`, in the same order, `None`
+/// where the field was absent. Pre-1.18 worlds wrap most chunk fields in a `Level` compound; any
+/// target still missing when a `Level` tag is found is looked for inside it too.
+pub fn query_tags(chunk_data: &[u8], targets: &[&str]) -> Result<Vec<Option<TagValue>>, NbtError> {
     // Prefetch the beginning of the chunk data into cache
     if chunk_data.len() >= 64 {
         #[cfg(target_arch = "x86_64")]
@@ -69,114 +121,67 @@ pub fn extract_inhabited_time(chunk_data: &[u8]) -> Result<Option<i64>, NbtError
     // Skip root tag name
     skip_string(&mut cursor)?;
 
-    // Search through the root compound for InhabitedTime
-    search_compound(&mut cursor, 2)
-}
-
-fn search_compound<R: Read>(reader: &mut R, max_depth: u8) -> Result<Option<i64>, NbtError> {
-    search_compound_recursive(reader, max_depth, 0)
-}
-
-/// Check if a string matches "InhabitedTime" without allocating
-#[inline(always)]
-fn is_inhabited_time_string<R: Read>(reader: &mut R) -> Result<bool, NbtError> {
-    let length = reader.read_u16::<BigEndian>()? as usize;
-
-    const INHABITED_TIME: &[u8] = b"InhabitedTime";
-
-    if length != INHABITED_TIME.len() {
-        skip_bytes(reader, length)?;
-        return Ok(false);
-    }
-
-    // Read the string data into a buffer
-    let mut buffer = [0u8; 13];
-    reader.read_exact(&mut buffer)?;
-
-    // Use SIMD-optimized comparison
-    Ok(compare_inhabited_time(&buffer))
-}
-
-#[inline(always)]
-pub fn compare_inhabited_time(buffer: &[u8; 13]) -> bool {
-    const INHABITED_TIME: &[u8] = b"InhabitedTime";
-
-    // Compare in 8-byte chunks using u64
-    // "InhabitedTime" = 13 bytes, so we compare 8 bytes + 5 bytes
-
-    // First 8 bytes: "Inhabite"
-    let chunk1_buffer = u64::from_ne_bytes([
-        buffer[0], buffer[1], buffer[2], buffer[3], buffer[4], buffer[5], buffer[6], buffer[7],
-    ]);
-    let chunk1_target = u64::from_ne_bytes([
-        INHABITED_TIME[0],
-        INHABITED_TIME[1],
-        INHABITED_TIME[2],
-        INHABITED_TIME[3],
-        INHABITED_TIME[4],
-        INHABITED_TIME[5],
-        INHABITED_TIME[6],
-        INHABITED_TIME[7],
-    ]);
-
-    if chunk1_buffer != chunk1_target {
-        return false;
-    }
-
-    // Remaining 5 bytes: "dTime"
-    let chunk2_buffer = u64::from_ne_bytes([
-        buffer[8], buffer[9], buffer[10], buffer[11], buffer[12], 0, 0, 0,
-    ]);
-    let chunk2_target = u64::from_ne_bytes([
-        INHABITED_TIME[8],
-        INHABITED_TIME[9],
-        INHABITED_TIME[10],
-        INHABITED_TIME[11],
-        INHABITED_TIME[12],
-        0,
-        0,
-        0,
-    ]);
-
-    chunk2_buffer == chunk2_target
+    let mut results = vec![None; targets.len()];
+    scan_compound_for_tags(&mut cursor, targets, &mut results, 0)?;
+    Ok(results)
 }
 
-fn search_compound_recursive<R: Read>(
+fn scan_compound_for_tags<R: Read>(
     reader: &mut R,
-    max_depth: u8,
-    current_depth: u8,
-) -> Result<Option<i64>, NbtError> {
+    targets: &[&str],
+    results: &mut [Option<TagValue>],
+    depth: u8,
+) -> Result<(), NbtError> {
     loop {
         let tag_type = reader.read_u8()?;
         if tag_type == TAG_END {
-            return Ok(None);
+            return Ok(());
         }
 
-        // Check if this is "InhabitedTime" without allocating
-        let is_inhabited_time = is_inhabited_time_string(reader)?;
+        let length = reader.read_u16::<BigEndian>()? as usize;
+        if length > MAX_TAG_NAME_LEN {
+            skip_bytes(reader, length)?;
+            skip_tag_value(reader, tag_type)?;
+            continue;
+        }
 
-        if !is_inhabited_time {
-            // For compounds, recurse if within depth limit - this is less common
-            if tag_type == TAG_COMPOUND && current_depth < max_depth {
-                if let Some(result) =
-                    search_compound_recursive(reader, max_depth, current_depth + 1)?
-                {
-                    return Ok(Some(result));
+        let mut name = [0u8; MAX_TAG_NAME_LEN];
+        reader.read_exact(&mut name[..length])?;
+        let name = &name[..length];
+
+        let is_level = name == b"Level" && depth < MAX_LEVEL_DEPTH;
+        let matched = targets.iter().position(|target| target.as_bytes() == name);
+
+        match matched {
+            Some(index) if results[index].is_none() => {
+                if is_level && tag_type == TAG_COMPOUND {
+                    // `Level` was itself a target (presence/type check) - record it, then still
+                    // descend so any other still-missing target nested inside it is found.
+                    results[index] = Some(TagValue::Other(tag_type));
+                    scan_compound_for_tags(reader, targets, results, depth + 1)?;
+                } else {
+                    results[index] = Some(read_tag_value(reader, tag_type)?);
                 }
-            } else {
-                skip_tag_value(reader, tag_type)?;
             }
-        } else {
-            return match tag_type {
-                TAG_LONG => Ok(Some(reader.read_i64::<BigEndian>()?)),
-                TAG_INT => Ok(Some(reader.read_i32::<BigEndian>()? as i64)),
-                TAG_SHORT => Ok(Some(reader.read_i16::<BigEndian>()? as i64)),
-                TAG_BYTE => Ok(Some(reader.read_i8()? as i64)),
-                _ => Err(NbtError::InvalidFormat(format!(
-                    "InhabitedTime has unexpected type: {}",
-                    tag_type
-                ))),
-            };
+            _ if is_level && tag_type == TAG_COMPOUND => {
+                scan_compound_for_tags(reader, targets, results, depth + 1)?;
+            }
+            _ => skip_tag_value(reader, tag_type)?,
+        }
+    }
+}
+
+fn read_tag_value<R: Read>(reader: &mut R, tag_type: u8) -> Result<TagValue, NbtError> {
+    match tag_type {
+        TAG_BYTE => Ok(TagValue::Byte(reader.read_i8()?)),
+        TAG_SHORT => Ok(TagValue::Short(reader.read_i16::<BigEndian>()?)),
+        TAG_INT => Ok(TagValue::Int(reader.read_i32::<BigEndian>()?)),
+        TAG_LONG => Ok(TagValue::Long(reader.read_i64::<BigEndian>()?)),
+        TAG_FLOAT => Ok(TagValue::Float(reader.read_f32::<BigEndian>()?)),
+        TAG_DOUBLE => Ok(TagValue::Double(reader.read_f64::<BigEndian>()?)),
+        _ => {
+            skip_tag_value(reader, tag_type)?;
+            Ok(TagValue::Other(tag_type))
         }
     }
 }
@@ -286,7 +291,10 @@ fn skip_compound<R: Read>(reader: &mut R) -> Result<(), NbtError> {
     Ok(())
 }
 
-pub fn process_chunk(chunk_data: &[u8]) -> Result<Option<i64>, ProcessError> {
-    extract_inhabited_time(chunk_data)
+pub fn process_chunk(
+    chunk_data: &[u8],
+    targets: &[&str],
+) -> Result<Vec<Option<TagValue>>, ProcessError> {
+    query_tags(chunk_data, targets)
         .map_err(|e| ProcessError::ChunkError(format!("Fast NBT parsing failed: {}", e)))
 }
@@ -0,0 +1,92 @@
+use crate::parser::TagValue;
+
+/// The tag values looked up for one chunk, keyed by the field names a [`RetentionPredicate`]
+/// declared via [`RetentionPredicate::required_tags`].
+pub struct TagValues<'a> {
+    names: &'a [&'a str],
+    values: &'a [Option<TagValue>],
+}
+
+impl<'a> TagValues<'a> {
+    pub fn new(names: &'a [&'a str], values: &'a [Option<TagValue>]) -> Self {
+        Self { names, values }
+    }
+
+    pub fn get(&self, name: &str) -> Option<TagValue> {
+        let index = self.names.iter().position(|n| *n == name)?;
+        self.values[index]
+    }
+}
+
+/// Decides whether a chunk survives pruning. Implementations declare the tag fields they need
+/// via `required_tags`, and `process_region` extracts those fields in a single NBT scan before
+/// calling `should_keep`.
+pub trait RetentionPredicate: Send + Sync {
+    /// Tag names this predicate needs extracted from each chunk.
+    fn required_tags(&self) -> Vec<&'static str>;
+
+    /// Whether the chunk should be kept, given the values looked up for `required_tags()`.
+    fn should_keep(&self, values: &TagValues) -> bool;
+}
+
+/// Keep chunks whose `InhabitedTime` exceeds `threshold`.
+pub struct InhabitedTimePredicate {
+    pub threshold: i64,
+}
+
+impl InhabitedTimePredicate {
+    pub fn new(threshold: i64) -> Self {
+        Self { threshold }
+    }
+}
+
+impl RetentionPredicate for InhabitedTimePredicate {
+    fn required_tags(&self) -> Vec<&'static str> {
+        vec!["InhabitedTime"]
+    }
+
+    fn should_keep(&self, values: &TagValues) -> bool {
+        match values.get("InhabitedTime").and_then(TagValue::as_i64) {
+            Some(inhabited_time) => inhabited_time > self.threshold,
+            None => false,
+        }
+    }
+}
+
+/// Keep a chunk if any wrapped predicate would keep it.
+pub struct Any(pub Vec<Box<dyn RetentionPredicate>>);
+
+/// Keep a chunk only if every wrapped predicate would keep it.
+pub struct All(pub Vec<Box<dyn RetentionPredicate>>);
+
+fn merged_tags(predicates: &[Box<dyn RetentionPredicate>]) -> Vec<&'static str> {
+    let mut tags = Vec::new();
+    for predicate in predicates {
+        for tag in predicate.required_tags() {
+            if !tags.contains(&tag) {
+                tags.push(tag);
+            }
+        }
+    }
+    tags
+}
+
+impl RetentionPredicate for Any {
+    fn required_tags(&self) -> Vec<&'static str> {
+        merged_tags(&self.0)
+    }
+
+    fn should_keep(&self, values: &TagValues) -> bool {
+        self.0.iter().any(|predicate| predicate.should_keep(values))
+    }
+}
+
+impl RetentionPredicate for All {
+    fn required_tags(&self) -> Vec<&'static str> {
+        merged_tags(&self.0)
+    }
+
+    fn should_keep(&self, values: &TagValues) -> bool {
+        self.0.iter().all(|predicate| predicate.should_keep(values))
+    }
+}
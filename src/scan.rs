@@ -0,0 +1,336 @@
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use flate2::read::{GzDecoder, ZlibDecoder};
+
+const HEADER_SIZE: usize = 8192;
+const SECTOR_SIZE: usize = 4096;
+const LOCATION_TABLE_ENTRIES: usize = 1024;
+
+#[derive(Debug)]
+pub enum ScanError {
+    IoError(std::io::Error),
+    InvalidFormat(String),
+}
+
+impl From<std::io::Error> for ScanError {
+    fn from(error: std::io::Error) -> Self {
+        ScanError::IoError(error)
+    }
+}
+
+impl std::fmt::Display for ScanError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScanError::IoError(e) => write!(f, "IO error: {}", e),
+            ScanError::InvalidFormat(msg) => write!(f, "Invalid region format: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for ScanError {}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct CorruptionStats {
+    pub bad_offset: u32,
+    pub oversized: u32,
+    pub bad_compression: u32,
+    pub inflate_failed: u32,
+    pub overlapping: u32,
+}
+
+impl CorruptionStats {
+    pub fn merge(&mut self, other: CorruptionStats) {
+        self.bad_offset += other.bad_offset;
+        self.oversized += other.oversized;
+        self.bad_compression += other.bad_compression;
+        self.inflate_failed += other.inflate_failed;
+        self.overlapping += other.overlapping;
+    }
+
+    pub fn total(&self) -> u32 {
+        self.bad_offset
+            + self.oversized
+            + self.bad_compression
+            + self.inflate_failed
+            + self.overlapping
+    }
+}
+
+/// Result of validating a region's raw Anvil header against its payload sectors.
+#[derive(Debug, Default)]
+pub struct ScanReport {
+    pub stats: CorruptionStats,
+    /// Chunk coordinates flagged as corrupt, in location-table order.
+    pub corrupt_chunks: Vec<(usize, usize)>,
+}
+
+/// Validate the raw 8 KiB Anvil header and every chunk payload it points at, without relying on
+/// `fastanvil` to trust the data. A chunk is flagged as corrupt when its declared sector offset
+/// lands past EOF, its declared length exceeds its reserved sectors, its compression byte is not
+/// 1 (gzip), 2 (zlib) or 3 (uncompressed), its compressed stream fails to inflate, or its sectors
+/// overlap a chunk already claimed earlier in the table.
+pub fn scan_region(data: &[u8]) -> Result<ScanReport, ScanError> {
+    if data.len() < HEADER_SIZE {
+        return Err(ScanError::InvalidFormat(format!(
+            "file is {} bytes, smaller than the {}-byte header",
+            data.len(),
+            HEADER_SIZE
+        )));
+    }
+
+    let total_sectors = data.len() / SECTOR_SIZE;
+    let mut claimed = vec![false; total_sectors];
+
+    let mut report = ScanReport::default();
+
+    for index in 0..LOCATION_TABLE_ENTRIES {
+        let entry = &data[index * 4..index * 4 + 4];
+        let sector_offset = u32::from_be_bytes([0, entry[0], entry[1], entry[2]]) as usize;
+        let sector_count = entry[3] as usize;
+
+        if sector_offset == 0 && sector_count == 0 {
+            continue;
+        }
+
+        let x = index % 32;
+        let z = index / 32;
+
+        let start_byte = sector_offset * SECTOR_SIZE;
+        let span_bytes = sector_count * SECTOR_SIZE;
+
+        if sector_count == 0 || start_byte + span_bytes > data.len() {
+            report.stats.bad_offset += 1;
+            report.corrupt_chunks.push((x, z));
+            continue;
+        }
+
+        if (sector_offset..sector_offset + sector_count)
+            .any(|sector| claimed.get(sector).copied().unwrap_or(true))
+        {
+            report.stats.overlapping += 1;
+            report.corrupt_chunks.push((x, z));
+            continue;
+        }
+
+        let payload = &data[start_byte..start_byte + span_bytes];
+        let declared_len =
+            u32::from_be_bytes([payload[0], payload[1], payload[2], payload[3]]) as usize;
+
+        if declared_len == 0 || declared_len - 1 > span_bytes - 5 {
+            report.stats.oversized += 1;
+            report.corrupt_chunks.push((x, z));
+            continue;
+        }
+
+        let compression = payload[4];
+        let body = &payload[5..5 + declared_len - 1];
+
+        let inflates = match compression {
+            1 => inflates_ok(GzDecoder::new(body)),
+            2 => inflates_ok(ZlibDecoder::new(body)),
+            3 => true,
+            _ => {
+                report.stats.bad_compression += 1;
+                report.corrupt_chunks.push((x, z));
+                continue;
+            }
+        };
+
+        if !inflates {
+            report.stats.inflate_failed += 1;
+            report.corrupt_chunks.push((x, z));
+            continue;
+        }
+
+        claimed[sector_offset..sector_offset + sector_count].fill(true);
+    }
+
+    Ok(report)
+}
+
+fn inflates_ok<R: Read>(mut decoder: R) -> bool {
+    std::io::copy(&mut decoder, &mut std::io::sink()).is_ok()
+}
+
+/// Whether chunk `(x, z)`'s raw location-table entry in `data` describes a sector range that
+/// actually fits inside the file - the same bounds check `scan_region` uses to flag `bad_offset`.
+/// Corruption categories found further down the pipeline (`overlapping`, `bad_compression`,
+/// `inflate_failed`) all pass this check, since they're only ever reached once the sector range
+/// itself has already been validated; only `bad_offset` chunks fail it.
+pub fn location_in_bounds(data: &[u8], x: usize, z: usize) -> bool {
+    if data.len() < HEADER_SIZE {
+        return false;
+    }
+
+    let index = z * 32 + x;
+    let entry = &data[index * 4..index * 4 + 4];
+    let sector_offset = u32::from_be_bytes([0, entry[0], entry[1], entry[2]]) as usize;
+    let sector_count = entry[3] as usize;
+
+    if sector_offset == 0 && sector_count == 0 {
+        return true;
+    }
+
+    sector_count != 0 && sector_offset * SECTOR_SIZE + sector_count * SECTOR_SIZE <= data.len()
+}
+
+/// Zero out the location-table entries for the given chunks so the region stays a valid (if
+/// sparser) Anvil file without rewriting every sector, mirroring how pruning drops chunks.
+pub fn delete_corrupted(path: &Path, corrupt_chunks: &[(usize, usize)]) -> Result<(), ScanError> {
+    let mut file = std::fs::OpenOptions::new().write(true).open(path)?;
+
+    for &(x, z) in corrupt_chunks {
+        let index = z * 32 + x;
+        file.seek(SeekFrom::Start((index * 4) as u64))?;
+        file.write_all(&[0u8; 4])?;
+    }
+
+    Ok(())
+}
+
+/// Carry each corrupt chunk's raw, still-compressed payload over from `original` into `path`
+/// unchanged, by appending it past the current end of file and repointing its location-table
+/// entry there. Used after a full region rebuild, when `--delete-corrupted` was not requested, so
+/// corrupt chunks are not silently lost just because the region was rewritten for an unrelated
+/// reason. A chunk whose sector range was itself out of bounds in `original` (the `bad_offset`
+/// corruption case) has nothing safe to copy and is left as a hole rather than fabricated.
+pub fn preserve_corrupted(
+    path: &Path,
+    original: &[u8],
+    corrupt_chunks: &[(usize, usize)],
+) -> Result<(), ScanError> {
+    if corrupt_chunks.is_empty() {
+        return Ok(());
+    }
+
+    let mut file = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(path)?;
+    let file_len = file.metadata()?.len() as usize;
+    let mut next_sector = file_len.div_ceil(SECTOR_SIZE);
+
+    for &(x, z) in corrupt_chunks {
+        let index = z * 32 + x;
+        let entry = &original[index * 4..index * 4 + 4];
+        let sector_offset = u32::from_be_bytes([0, entry[0], entry[1], entry[2]]) as usize;
+        let sector_count = entry[3] as usize;
+
+        if sector_count == 0 {
+            continue;
+        }
+
+        let start = sector_offset * SECTOR_SIZE;
+        let span = sector_count * SECTOR_SIZE;
+        if start + span > original.len() {
+            continue;
+        }
+
+        file.seek(SeekFrom::Start((next_sector * SECTOR_SIZE) as u64))?;
+        file.write_all(&original[start..start + span])?;
+
+        let offset_bytes = (next_sector as u32).to_be_bytes();
+        file.seek(SeekFrom::Start((index * 4) as u64))?;
+        file.write_all(&[
+            offset_bytes[1],
+            offset_bytes[2],
+            offset_bytes[3],
+            sector_count as u8,
+        ])?;
+
+        next_sector += sector_count;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::ZlibEncoder;
+    use flate2::Compression;
+
+    /// Build a minimal region buffer with a single chunk at (0, 0), sector 2, whose compression
+    /// byte is `compression` and whose payload is `body` zlib-compressed when `compression` is 2,
+    /// or stored as-is for any other value.
+    fn region_with_chunk(compression: u8, body: &[u8]) -> Vec<u8> {
+        let compressed = match compression {
+            2 => {
+                let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+                encoder.write_all(body).unwrap();
+                encoder.finish().unwrap()
+            }
+            _ => body.to_vec(),
+        };
+
+        let mut payload = Vec::new();
+        payload.extend_from_slice(&((compressed.len() + 1) as u32).to_be_bytes());
+        payload.push(compression);
+        payload.extend_from_slice(&compressed);
+
+        let sector_count = payload.len().div_ceil(SECTOR_SIZE).max(1);
+        payload.resize(sector_count * SECTOR_SIZE, 0);
+
+        let mut data = vec![0u8; HEADER_SIZE];
+        data[0] = 0;
+        data[1] = 0;
+        data[2] = 2; // sector offset
+        data[3] = sector_count as u8;
+        data.extend_from_slice(&payload);
+        data
+    }
+
+    #[test]
+    fn valid_chunk_is_not_flagged() {
+        let data = region_with_chunk(2, b"hello chunk");
+        let report = scan_region(&data).unwrap();
+        assert_eq!(report.stats.total(), 0);
+        assert!(report.corrupt_chunks.is_empty());
+    }
+
+    #[test]
+    fn offset_past_eof_is_flagged() {
+        let mut data = vec![0u8; HEADER_SIZE];
+        // Declares 1 sector at an offset far beyond the (header-only) file.
+        data[0] = 0;
+        data[1] = 0x01;
+        data[2] = 0x00;
+        data[3] = 1;
+
+        let report = scan_region(&data).unwrap();
+        assert_eq!(report.stats.bad_offset, 1);
+        assert_eq!(report.corrupt_chunks, vec![(0, 0)]);
+    }
+
+    #[test]
+    fn overlapping_sectors_are_flagged() {
+        let mut data = region_with_chunk(2, b"first chunk");
+        // Point chunk (1, 0)'s entry at the same sector as chunk (0, 0).
+        data[4] = 0;
+        data[5] = 0;
+        data[6] = 2;
+        data[7] = 1;
+
+        let report = scan_region(&data).unwrap();
+        assert_eq!(report.stats.overlapping, 1);
+        assert_eq!(report.corrupt_chunks, vec![(1, 0)]);
+    }
+
+    #[test]
+    fn bad_compression_byte_is_flagged() {
+        let data = region_with_chunk(9, b"irrelevant");
+        let report = scan_region(&data).unwrap();
+        assert_eq!(report.stats.bad_compression, 1);
+        assert_eq!(report.corrupt_chunks, vec![(0, 0)]);
+    }
+
+    #[test]
+    fn undersized_buffer_is_rejected() {
+        let data = vec![0u8; HEADER_SIZE - 1];
+        assert!(matches!(
+            scan_region(&data),
+            Err(ScanError::InvalidFormat(_))
+        ));
+    }
+}